@@ -1,38 +1,132 @@
 //! A simple Rust program demonstrating various language features
 
+// This is a demonstration file, not a library — several methods and enum
+// variants exist to show the shape of an API (e.g. a custom timestamp
+// format, magazine items) without every one of them being exercised by
+// the small `main` below.
+#![allow(dead_code)]
+
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::{mpsc, oneshot};
 
-/// Represents a book in our library
+/// Data specific to a book.
 #[derive(Debug, Clone)]
-struct Book {
-    title: String,
+struct BookData {
     author: String,
-    year: u16,
-    isbn: String,
     genres: Vec<String>,
 }
 
-impl Book {
-    /// Creates a new Book instance
-    fn new(title: &str, author: &str, year: u16, isbn: &str) -> Self {
-        Book {
+/// Data specific to a CD.
+#[derive(Debug, Clone)]
+struct CdData {
+    artist: String,
+    runtime_minutes: u32,
+}
+
+/// Data specific to a DVD.
+#[derive(Debug, Clone)]
+struct DvdData {
+    director: String,
+    runtime_minutes: u32,
+}
+
+/// Data specific to a magazine.
+#[derive(Debug, Clone)]
+struct MagazineData {
+    publisher: String,
+    issue_number: u32,
+}
+
+/// The kind of media a `LoanableItem` represents, along with the fields
+/// that only make sense for that kind.
+#[derive(Debug, Clone)]
+enum ItemKind {
+    Book(BookData),
+    Cd(CdData),
+    Dvd(DvdData),
+    Magazine(MagazineData),
+}
+
+/// A single item the library can catalog and lend out, regardless of
+/// what kind of media it is.
+#[derive(Debug, Clone)]
+struct LoanableItem {
+    id: String,
+    title: String,
+    year: u16,
+    return_date: Option<String>,
+    kind: ItemKind,
+}
+
+impl LoanableItem {
+    /// Creates a new book item.
+    fn new_book(id: &str, title: &str, year: u16, author: &str) -> Self {
+        LoanableItem {
+            id: id.to_string(),
             title: title.to_string(),
-            author: author.to_string(),
             year,
-            isbn: isbn.to_string(),
-            genres: Vec::new(),
+            return_date: None,
+            kind: ItemKind::Book(BookData {
+                author: author.to_string(),
+                genres: Vec::new(),
+            }),
         }
     }
-    
-    /// Adds a genre to the book
+
+    /// Creates a new CD item.
+    fn new_cd(id: &str, title: &str, year: u16, artist: &str, runtime_minutes: u32) -> Self {
+        LoanableItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            year,
+            return_date: None,
+            kind: ItemKind::Cd(CdData {
+                artist: artist.to_string(),
+                runtime_minutes,
+            }),
+        }
+    }
+
+    /// Creates a new DVD item.
+    fn new_dvd(id: &str, title: &str, year: u16, director: &str, runtime_minutes: u32) -> Self {
+        LoanableItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            year,
+            return_date: None,
+            kind: ItemKind::Dvd(DvdData {
+                director: director.to_string(),
+                runtime_minutes,
+            }),
+        }
+    }
+
+    /// Creates a new magazine item.
+    fn new_magazine(id: &str, title: &str, year: u16, publisher: &str, issue_number: u32) -> Self {
+        LoanableItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            year,
+            return_date: None,
+            kind: ItemKind::Magazine(MagazineData {
+                publisher: publisher.to_string(),
+                issue_number,
+            }),
+        }
+    }
+
+    /// Adds a genre to the item, if it's a book.
     fn add_genre(&mut self, genre: &str) {
-        self.genres.push(genre.to_string());
+        if let ItemKind::Book(data) = &mut self.kind {
+            data.genres.push(genre.to_string());
+        }
     }
-    
-    /// Returns true if the book is considered a classic (over 50 years old)
+
+    /// Returns true if the item is considered a classic (over 50 years old)
     fn is_classic(&self) -> bool {
         // Current year - published year > 50
         2023 - self.year as u32 > 50
@@ -47,31 +141,48 @@ trait Borrowable {
     fn return_item(&mut self) -> Result<(), &'static str>;
 }
 
-impl Borrowable for Book {
+impl Borrowable for LoanableItem {
     fn get_id(&self) -> &str {
-        &self.isbn
+        &self.id
     }
-    
+
+    // This only covers whether an item is loanable *in principle* — a
+    // permanent, kind-specific restriction (old books are reference-only)
+    // rather than a statement about current loan state. Whether it's
+    // actually free right now is derived from the loan table, which only
+    // `Library` has; see `Library::is_available`.
     fn is_available(&self) -> bool {
-        // For this example, assume books published before 1900 are reference only
-        self.year >= 1900
+        match &self.kind {
+            // For this example, assume books published before 1900 are reference only
+            ItemKind::Book(_) => self.year >= 1900,
+            ItemKind::Magazine(_) => self.return_date.is_none(),
+            ItemKind::Cd(_) | ItemKind::Dvd(_) => true,
+        }
     }
-    
+
     fn borrow_item(&mut self) -> Result<(), &'static str> {
         if !self.is_available() {
-            return Err("This book cannot be borrowed");
+            return Err("This item cannot be borrowed");
         }
         Ok(())
     }
-    
+
     fn return_item(&mut self) -> Result<(), &'static str> {
         Ok(())
     }
 }
 
-/// A library that manages a collection of books
+/// A record of an outstanding loan: who has the item and when it's due back.
+#[derive(Debug, Clone)]
+struct Loan {
+    borrower: String,
+    due_date: SystemTime,
+}
+
+/// A library that manages a catalog of mixed media
 struct Library {
-    books: HashMap<String, Book>,
+    items: HashMap<String, LoanableItem>,
+    loans: HashMap<String, Loan>,
     name: String,
 }
 
@@ -79,66 +190,696 @@ impl Library {
     fn new(name: &str) -> Self {
         Library {
             name: name.to_string(),
-            books: HashMap::new(),
+            items: HashMap::new(),
+            loans: HashMap::new(),
         }
     }
-    
-    fn add_book(&mut self, book: Book) {
-        self.books.insert(book.isbn.clone(), book);
+
+    /// Returns true if the item exists, is loanable at all (e.g. not a
+    /// reference-only book), and isn't currently checked out. This is the
+    /// check that's actually derived from loan state — the per-kind
+    /// reference-only rule in `LoanableItem::is_available` is a
+    /// permanent restriction, not a loan-state one.
+    fn is_available(&self, id: &str) -> bool {
+        match self.items.get(id) {
+            Some(item) => item.is_available() && !self.loans.contains_key(id),
+            None => false,
+        }
     }
-    
-    fn get_book(&self, isbn: &str) -> Option<&Book> {
-        self.books.get(isbn)
+
+    /// Checks an item out to `borrower` for `loan_days`, recording a due date.
+    fn check_out(&mut self, isbn: &str, borrower: &str, loan_days: u32) -> Result<(), &'static str> {
+        if !self.items.contains_key(isbn) {
+            return Err("No such item in the catalog");
+        }
+        if !self.is_available(isbn) {
+            return Err("Item is already out or reference-only");
+        }
+        let due_date = SystemTime::now() + Duration::from_secs(loan_days as u64 * 86_400);
+        self.loans.insert(
+            isbn.to_string(),
+            Loan {
+                borrower: borrower.to_string(),
+                due_date,
+            },
+        );
+        Ok(())
     }
-    
-    fn get_books_by_author(&self, author: &str) -> Vec<&Book> {
-        self.books.values()
-            .filter(|book| book.author.contains(author))
+
+    /// Checks an item back in, clearing its loan record.
+    fn check_in(&mut self, isbn: &str) -> Result<(), &'static str> {
+        if self.loans.remove(isbn).is_none() {
+            return Err("Item is not currently on loan");
+        }
+        Ok(())
+    }
+
+    /// Returns every item that's currently checked out.
+    fn currently_loaned(&self) -> Vec<&LoanableItem> {
+        self.loans.keys()
+            .filter_map(|id| self.items.get(id))
             .collect()
     }
-    
+
+    /// Returns every item whose due date has already passed as of `now`.
+    fn overdue(&self, now: SystemTime) -> Vec<&LoanableItem> {
+        self.loans.iter()
+            .filter(|(_, loan)| loan.due_date < now)
+            .filter_map(|(id, _)| self.items.get(id))
+            .collect()
+    }
+
+    /// Returns every item currently out to the given borrower.
+    fn loans_for_borrower(&self, borrower: &str) -> Vec<&LoanableItem> {
+        self.loans.iter()
+            .filter(|(_, loan)| loan.borrower == borrower)
+            .filter_map(|(id, _)| self.items.get(id))
+            .collect()
+    }
+
+    fn add_book(&mut self, book: LoanableItem) {
+        self.items.insert(book.id.clone(), book);
+    }
+
+    fn get_book(&self, isbn: &str) -> Option<&LoanableItem> {
+        self.items.get(isbn)
+    }
+
+    fn get_books_by_author(&self, author: &str) -> Vec<&LoanableItem> {
+        self.items.values()
+            .filter(|item| matches!(&item.kind, ItemKind::Book(data) if data.author.contains(author)))
+            .collect()
+    }
+
+    /// Returns every item of the given kind in the catalog (e.g. all CDs).
+    fn get_items_by_kind<F>(&self, predicate: F) -> Vec<&LoanableItem>
+    where
+        F: Fn(&ItemKind) -> bool,
+    {
+        self.items.values()
+            .filter(|item| predicate(&item.kind))
+            .collect()
+    }
+
     fn count_books(&self) -> usize {
-        self.books.len()
+        self.items.len()
+    }
+
+    /// Imports catalog rows using `schema` to convert each raw text field
+    /// into its typed value, then inserts a book-kind item per row.
+    /// Rows missing an `isbn`, `title`, or `year` column (or whose `year`
+    /// column the schema converts to something other than an integer) are
+    /// skipped; a `year` integer that doesn't fit in
+    /// a `u16`, or any other conversion failure, is returned as an error.
+    fn import_records(&mut self, rows: &[Vec<String>], schema: &ImportSchema) -> Result<usize, ConversionError> {
+        // Convert every row before inserting anything, so a conversion
+        // error partway through a batch leaves the catalog untouched
+        // rather than applying only the rows seen so far.
+        let mut items = Vec::new();
+        for row in rows {
+            let mut fields: HashMap<&str, FieldValue> = HashMap::new();
+            for ((name, conversion), raw) in schema.columns.iter().zip(row.iter()) {
+                fields.insert(name.as_str(), conversion.convert(raw)?);
+            }
+
+            let id = match fields.get("isbn") {
+                Some(FieldValue::String(s)) => s.clone(),
+                _ => continue,
+            };
+            let title = match fields.get("title") {
+                Some(FieldValue::String(s)) => s.clone(),
+                _ => continue,
+            };
+            let year = match fields.get("year") {
+                Some(FieldValue::Integer(n)) => u16::try_from(*n).map_err(|_| ConversionError {
+                    raw: n.to_string(),
+                    reason: "year is out of range for a u16".to_string(),
+                })?,
+                _ => continue,
+            };
+            let author = match fields.get("author") {
+                Some(FieldValue::String(s)) => s.clone(),
+                _ => String::new(),
+            };
+
+            items.push(LoanableItem::new_book(&id, &title, year, &author));
+        }
+
+        let imported = items.len();
+        for item in items {
+            self.add_book(item);
+        }
+        Ok(imported)
+    }
+
+    /// Exports the catalog as BibTeX-style citation entries, one per item,
+    /// ordered by id for a stable diff-friendly output.
+    fn export_bibtex(&self) -> String {
+        let mut items: Vec<_> = self.items.values().collect();
+        items.sort_by(|a, b| a.id.cmp(&b.id));
+        items.iter()
+            .map(|item| bibliography::item_to_entry(item).to_bibtex())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Imports items from BibTeX-style citation text produced by
+    /// `export_bibtex` (or any similarly-shaped `.bib` source).
+    fn import_bibtex(&mut self, text: &str) {
+        for entry in bibliography::parse(text) {
+            if let Some(item) = bibliography::entry_to_item(&entry) {
+                self.add_book(item);
+            }
+        }
     }
 }
 
-fn main() {
+/// BibTeX-style serialization of catalog items, for interchange with
+/// reference managers instead of being print-only.
+mod bibliography {
+    use super::{ItemKind, LoanableItem};
+    use std::collections::HashMap;
+
+    /// A single BibTeX-style entry: a citation key, an entry type (e.g.
+    /// `book`), and its `field = {value}` pairs.
+    #[derive(Debug, Clone)]
+    pub struct BibEntry {
+        pub key: String,
+        pub entry_type: String,
+        pub fields: HashMap<String, String>,
+    }
+
+    impl BibEntry {
+        /// Renders this entry in `@type{key, field={value}, ...}` form.
+        pub fn to_bibtex(&self) -> String {
+            let mut fields: Vec<_> = self.fields.iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            let body = fields.iter()
+                .map(|(name, value)| format!("  {} = {{{}}}", name, value))
+                .collect::<Vec<_>>()
+                .join(",\n");
+            format!("@{}{{{},\n{}\n}}", self.entry_type, self.key, body)
+        }
+    }
+
+    /// Converts a catalog item into its `BibEntry` representation, mapping
+    /// a book's genre list to a `keywords = {...}` field. BibTeX has no
+    /// native entry type for audio/video media, so CDs and DVDs both use
+    /// `misc` and carry a `medium` field (`cd`/`dvd`) to disambiguate on
+    /// the way back in.
+    pub fn item_to_entry(item: &LoanableItem) -> BibEntry {
+        let mut fields = HashMap::new();
+        fields.insert("title".to_string(), item.title.clone());
+        fields.insert("year".to_string(), item.year.to_string());
+
+        let entry_type = match &item.kind {
+            ItemKind::Book(data) => {
+                fields.insert("author".to_string(), data.author.clone());
+                if !data.genres.is_empty() {
+                    fields.insert("keywords".to_string(), data.genres.join(", "));
+                }
+                "book"
+            }
+            ItemKind::Cd(data) => {
+                fields.insert("author".to_string(), data.artist.clone());
+                fields.insert("medium".to_string(), "cd".to_string());
+                fields.insert("runtime_minutes".to_string(), data.runtime_minutes.to_string());
+                "misc"
+            }
+            ItemKind::Dvd(data) => {
+                fields.insert("author".to_string(), data.director.clone());
+                fields.insert("medium".to_string(), "dvd".to_string());
+                fields.insert("runtime_minutes".to_string(), data.runtime_minutes.to_string());
+                "misc"
+            }
+            ItemKind::Magazine(data) => {
+                fields.insert("publisher".to_string(), data.publisher.clone());
+                fields.insert("number".to_string(), data.issue_number.to_string());
+                "article"
+            }
+        };
+
+        BibEntry {
+            key: item.id.clone(),
+            entry_type: entry_type.to_string(),
+            fields,
+        }
+    }
+
+    /// Rebuilds the `ItemKind`-appropriate item from a parsed `BibEntry`,
+    /// dispatching on `entry.entry_type` (and, for `misc`, the `medium`
+    /// field `item_to_entry` stamped on CDs/DVDs). Returns `None` for an
+    /// entry type this catalog has no `ItemKind` for.
+    pub fn entry_to_item(entry: &BibEntry) -> Option<LoanableItem> {
+        let title = entry.fields.get("title").cloned().unwrap_or_default();
+        let year = entry.fields.get("year")
+            .and_then(|y| y.parse::<u16>().ok())
+            .unwrap_or(0);
+        let author = entry.fields.get("author").cloned().unwrap_or_default();
+
+        match entry.entry_type.as_str() {
+            "book" => {
+                let mut item = LoanableItem::new_book(&entry.key, &title, year, &author);
+                if let Some(keywords) = entry.fields.get("keywords") {
+                    for genre in keywords.split(',') {
+                        let genre = genre.trim();
+                        if !genre.is_empty() {
+                            item.add_genre(genre);
+                        }
+                    }
+                }
+                Some(item)
+            }
+            "article" => {
+                let publisher = entry.fields.get("publisher").cloned().unwrap_or_default();
+                let issue_number = entry.fields.get("number")
+                    .and_then(|n| n.parse::<u32>().ok())
+                    .unwrap_or(0);
+                Some(LoanableItem::new_magazine(&entry.key, &title, year, &publisher, issue_number))
+            }
+            "misc" => {
+                let runtime_minutes = entry.fields.get("runtime_minutes")
+                    .and_then(|m| m.parse::<u32>().ok())
+                    .unwrap_or(0);
+                match entry.fields.get("medium").map(String::as_str) {
+                    Some("cd") => Some(LoanableItem::new_cd(&entry.key, &title, year, &author, runtime_minutes)),
+                    Some("dvd") => Some(LoanableItem::new_dvd(&entry.key, &title, year, &author, runtime_minutes)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses the text of a `.bib`-style source into its entries.
+    pub fn parse(text: &str) -> Vec<BibEntry> {
+        let mut entries = Vec::new();
+        let mut rest = text;
+
+        while let Some(at) = rest.find('@') {
+            rest = &rest[at + 1..];
+            let Some(brace) = rest.find('{') else { break };
+            let entry_type = rest[..brace].trim().to_lowercase();
+            rest = &rest[brace + 1..];
+
+            // Find the closing brace that matches the entry's opening
+            // brace, tracking nesting depth so a field's own `{value}`
+            // braces don't end the entry early.
+            let mut depth = 1;
+            let mut end = None;
+            for (i, c) in rest.char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let Some(end) = end else { break };
+            let body = &rest[..end];
+            rest = &rest[end + 1..];
+
+            let Some(comma) = body.find(',') else { continue };
+            let key = body[..comma].trim().to_string();
+
+            let mut fields = HashMap::new();
+            for field in body[comma + 1..].split("},") {
+                let field = field.trim().trim_end_matches('}');
+                let Some(eq) = field.find('=') else { continue };
+                let name = field[..eq].trim().to_string();
+                let value = field[eq + 1..].trim().trim_start_matches('{').to_string();
+                if !name.is_empty() {
+                    fields.insert(name, value);
+                }
+            }
+
+            entries.push(BibEntry { key, entry_type, fields });
+        }
+
+        entries
+    }
+}
+
+/// A typed value produced by converting a raw text field during import.
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    Bytes(Vec<u8>),
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Timestamp(SystemTime),
+}
+
+/// An error produced while converting a raw field into a `FieldValue`.
+#[derive(Debug, Clone)]
+struct ConversionError {
+    raw: String,
+    reason: String,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not convert {:?}: {}", self.raw, self.reason)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// How to interpret a raw text field pulled from an imported CSV/TSV row.
+#[derive(Debug, Clone, PartialEq)]
+enum Conversion {
+    Bytes,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp,
+    TimestampFmt(String),
+}
+
+impl Conversion {
+    /// Converts a raw field into its typed `FieldValue` according to this
+    /// conversion's rules.
+    fn convert(&self, raw: &str) -> Result<FieldValue, ConversionError> {
+        match self {
+            Conversion::Bytes => Ok(FieldValue::Bytes(raw.as_bytes().to_vec())),
+            Conversion::String => Ok(FieldValue::String(raw.to_string())),
+            Conversion::Integer => raw.parse::<i64>()
+                .map(FieldValue::Integer)
+                .map_err(|e| ConversionError { raw: raw.to_string(), reason: e.to_string() }),
+            Conversion::Float => raw.parse::<f64>()
+                .map(FieldValue::Float)
+                .map_err(|e| ConversionError { raw: raw.to_string(), reason: e.to_string() }),
+            Conversion::Boolean => match raw.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Ok(FieldValue::Boolean(true)),
+                "false" | "0" | "no" => Ok(FieldValue::Boolean(false)),
+                _ => Err(ConversionError {
+                    raw: raw.to_string(),
+                    reason: "not a recognized boolean".to_string(),
+                }),
+            },
+            Conversion::Timestamp => parse_timestamp(raw, "%Y-%m-%d").map(FieldValue::Timestamp),
+            Conversion::TimestampFmt(pattern) => parse_timestamp(raw, pattern).map(FieldValue::Timestamp),
+        }
+    }
+}
+
+impl FromStr for Conversion {
+    type Err = ConversionError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bytes" => Ok(Conversion::Bytes),
+            "string" | "str" => Ok(Conversion::String),
+            "int" | "integer" => Ok(Conversion::Integer),
+            "float" => Ok(Conversion::Float),
+            "bool" | "boolean" => Ok(Conversion::Boolean),
+            "timestamp" => Ok(Conversion::Timestamp),
+            other => Err(ConversionError {
+                raw: other.to_string(),
+                reason: "unrecognized conversion name".to_string(),
+            }),
+        }
+    }
+}
+
+/// Parses `raw` against a small strftime-style `pattern` supporting only
+/// `%Y` (4-digit year), `%m` (month), and `%d` (day), returning the
+/// corresponding midnight UTC `SystemTime`.
+fn parse_timestamp(raw: &str, pattern: &str) -> Result<SystemTime, ConversionError> {
+    let err = |reason: &str| ConversionError { raw: raw.to_string(), reason: reason.to_string() };
+
+    let mut year = None;
+    let mut month = None;
+    let mut day = None;
+
+    let mut pat_chars = pattern.chars().peekable();
+    let mut raw_chars = raw.chars().peekable();
+
+    while let Some(pc) = pat_chars.next() {
+        if pc == '%' {
+            let (width, token) = match pat_chars.next() {
+                Some('Y') => (4, 'Y'),
+                Some('m') => (2, 'm'),
+                Some('d') => (2, 'd'),
+                Some(other) => return Err(err(&format!("unsupported format token %{}", other))),
+                None => return Err(err("dangling % at end of pattern")),
+            };
+            let mut digits = String::new();
+            for _ in 0..width {
+                match raw_chars.peek() {
+                    Some(c) if c.is_ascii_digit() => digits.push(raw_chars.next().unwrap()),
+                    _ => break,
+                }
+            }
+            if digits.is_empty() {
+                return Err(err("expected digits where pattern required them"));
+            }
+            let value = digits.parse::<i64>().map_err(|e| err(&e.to_string()))?;
+            match token {
+                'Y' => year = Some(value),
+                'm' => month = Some(value),
+                _ => day = Some(value),
+            }
+        } else {
+            match raw_chars.next() {
+                Some(rc) if rc == pc => {}
+                _ => return Err(err("does not match pattern")),
+            }
+        }
+    }
+    if raw_chars.next().is_some() {
+        return Err(err("trailing characters after pattern"));
+    }
+
+    let year = year.ok_or_else(|| err("pattern did not include %Y"))?;
+    let month = month.ok_or_else(|| err("pattern did not include %m"))?;
+    let day = day.ok_or_else(|| err("pattern did not include %d"))?;
+
+    let days = days_from_civil(year, month, day);
+    let offset = Duration::from_secs(days.unsigned_abs() * 86_400);
+    if days >= 0 {
+        Ok(SystemTime::UNIX_EPOCH + offset)
+    } else {
+        Ok(SystemTime::UNIX_EPOCH - offset)
+    }
+}
+
+/// Days since the Unix epoch for a given calendar date, using Howard
+/// Hinnant's `days_from_civil` algorithm.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// A column name paired with how to convert its raw text values.
+#[derive(Debug, Clone)]
+struct ImportSchema {
+    columns: Vec<(String, Conversion)>,
+}
+
+/// A read-only question the library actor can answer.
+enum LibraryQuery {
+    CountBooks,
+    CurrentlyLoaned,
+    LoansForBorrower(String),
+}
+
+/// The answer to a `LibraryQuery`.
+enum QueryResult {
+    Count(usize),
+    Items(Vec<LoanableItem>),
+}
+
+/// An operation submitted to the library actor. Every variant except
+/// `AddBook` carries a `oneshot` reply channel so the caller can await the
+/// result; `AddBook` is sent without waiting for a reply.
+enum Command {
+    AddBook {
+        item: LoanableItem,
+    },
+    CheckOut {
+        isbn: String,
+        borrower: String,
+        loan_days: u32,
+        reply: oneshot::Sender<Result<(), &'static str>>,
+    },
+    CheckIn {
+        isbn: String,
+        reply: oneshot::Sender<Result<(), &'static str>>,
+    },
+    Query {
+        query: LibraryQuery,
+        reply: oneshot::Sender<QueryResult>,
+    },
+}
+
+/// The actor task: owns the `Library` by value and serializes every
+/// mutation by processing commands one at a time off the channel. No
+/// `Mutex` is needed because only this task ever touches the library.
+async fn run_library(mut library: Library, mut commands: mpsc::Receiver<Command>) {
+    while let Some(command) = commands.recv().await {
+        match command {
+            Command::AddBook { item } => library.add_book(item),
+            Command::CheckOut { isbn, borrower, loan_days, reply } => {
+                let _ = reply.send(library.check_out(&isbn, &borrower, loan_days));
+            }
+            Command::CheckIn { isbn, reply } => {
+                let _ = reply.send(library.check_in(&isbn));
+            }
+            Command::Query { query, reply } => {
+                let result = match query {
+                    LibraryQuery::CountBooks => QueryResult::Count(library.count_books()),
+                    LibraryQuery::CurrentlyLoaned => QueryResult::Items(
+                        library.currently_loaned().into_iter().cloned().collect(),
+                    ),
+                    LibraryQuery::LoansForBorrower(borrower) => QueryResult::Items(
+                        library.loans_for_borrower(&borrower).into_iter().cloned().collect(),
+                    ),
+                };
+                let _ = reply.send(result);
+            }
+        }
+    }
+}
+
+/// A cheaply cloneable client handle for the library actor. Each client
+/// task submits `Command`s over the shared `Sender` and awaits its own
+/// `oneshot` reply, so many tasks can use the library concurrently
+/// without contending on a lock.
+#[derive(Clone)]
+struct LibraryHandle {
+    commands: mpsc::Sender<Command>,
+}
+
+impl LibraryHandle {
+    /// Spawns the actor task owning `library` and returns a handle to it.
+    fn new(library: Library) -> Self {
+        let (tx, rx) = mpsc::channel(32);
+        tokio::spawn(run_library(library, rx));
+        LibraryHandle { commands: tx }
+    }
+
+    async fn add_book(&self, item: LoanableItem) {
+        let _ = self.commands.send(Command::AddBook { item }).await;
+    }
+
+    async fn check_out(&self, isbn: &str, borrower: &str, loan_days: u32) -> Result<(), &'static str> {
+        let (reply, recv) = oneshot::channel();
+        self.commands
+            .send(Command::CheckOut {
+                isbn: isbn.to_string(),
+                borrower: borrower.to_string(),
+                loan_days,
+                reply,
+            })
+            .await
+            .map_err(|_| "Library actor is gone")?;
+        recv.await.map_err(|_| "Library actor dropped the reply")?
+    }
+
+    async fn check_in(&self, isbn: &str) -> Result<(), &'static str> {
+        let (reply, recv) = oneshot::channel();
+        self.commands
+            .send(Command::CheckIn { isbn: isbn.to_string(), reply })
+            .await
+            .map_err(|_| "Library actor is gone")?;
+        recv.await.map_err(|_| "Library actor dropped the reply")?
+    }
+
+    async fn count_books(&self) -> usize {
+        let (reply, recv) = oneshot::channel();
+        if self.commands.send(Command::Query { query: LibraryQuery::CountBooks, reply }).await.is_err() {
+            return 0;
+        }
+        match recv.await {
+            Ok(QueryResult::Count(n)) => n,
+            _ => 0,
+        }
+    }
+
+    async fn loans_for_borrower(&self, borrower: &str) -> Vec<LoanableItem> {
+        let (reply, recv) = oneshot::channel();
+        let query = LibraryQuery::LoansForBorrower(borrower.to_string());
+        if self.commands.send(Command::Query { query, reply }).await.is_err() {
+            return Vec::new();
+        }
+        match recv.await {
+            Ok(QueryResult::Items(items)) => items,
+            _ => Vec::new(),
+        }
+    }
+
+    async fn currently_loaned(&self) -> Vec<LoanableItem> {
+        let (reply, recv) = oneshot::channel();
+        let query = LibraryQuery::CurrentlyLoaned;
+        if self.commands.send(Command::Query { query, reply }).await.is_err() {
+            return Vec::new();
+        }
+        match recv.await {
+            Ok(QueryResult::Items(items)) => items,
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
     // Create some books
-    let mut book1 = Book::new(
-        "The Great Gatsby", 
-        "F. Scott Fitzgerald", 
-        1925, 
-        "9780743273565"
+    let mut book1 = LoanableItem::new_book(
+        "9780743273565",
+        "The Great Gatsby",
+        1925,
+        "F. Scott Fitzgerald"
     );
     book1.add_genre("Classic");
     book1.add_genre("Fiction");
-    
-    let mut book2 = Book::new(
-        "To Kill a Mockingbird", 
-        "Harper Lee", 
-        1960, 
-        "9780061120084"
+
+    let mut book2 = LoanableItem::new_book(
+        "9780061120084",
+        "To Kill a Mockingbird",
+        1960,
+        "Harper Lee"
     );
     book2.add_genre("Fiction");
-    
-    let mut book3 = Book::new(
-        "1984", 
-        "George Orwell", 
-        1949, 
-        "9780451524935"
+
+    let mut book3 = LoanableItem::new_book(
+        "9780451524935",
+        "1984",
+        1949,
+        "George Orwell"
     );
     book3.add_genre("Dystopian");
-    
+
+    // The catalog isn't limited to books
+    let cd1 = LoanableItem::new_cd("0094638241621", "Abbey Road", 1969, "The Beatles", 47);
+
     // Create a library
     let mut library = Library::new("City Central Library");
     library.add_book(book1);
     library.add_book(book2);
     library.add_book(book3);
-    
+    library.add_book(cd1);
+
     // Print information about the library
     println!("Library: {}", library.name);
     println!("Number of books: {}", library.count_books());
-    
+
+    // Demonstrate mixed media lookup
+    let cds = library.get_items_by_kind(|kind| matches!(kind, ItemKind::Cd(_)));
+    println!("Number of CDs: {}", cds.len());
+
     // Demonstrate some pattern matching
     if let Some(book) = library.get_book("9780061120084") {
         match book.year {
@@ -147,15 +888,15 @@ fn main() {
             _ => println!("Recent book: {}", book.title),
         }
     }
-    
+
     // Demonstrate iterator methods
-    let classic_count = library.books.values()
+    let classic_count = library.items.values()
         .filter(|book| book.is_classic())
         .count();
     println!("Number of classics: {}", classic_count);
-    
+
     // Demonstrate closures
-    let old_books: Vec<_> = library.books.values()
+    let old_books: Vec<_> = library.items.values()
         .filter(|b| b.year < 1950)
         .map(|b| &b.title)
         .collect();
@@ -167,26 +908,74 @@ fn main() {
         Some(book) => println!("Found book: {}", book.title),
         None => println!("Book with ISBN {} not found", isbn),
     }
-    
-    // Demonstrate threading with shared state
-    let library_arc = Arc::new(Mutex::new(library));
-    
-    let mut handles = vec![];
-    
+
+    // Demonstrate the checkout/return subsystem
+    match library.check_out(isbn, "alice", 14) {
+        Ok(()) => println!("Checked out {} to alice", isbn),
+        Err(e) => println!("Could not check out {}: {}", isbn, e),
+    }
+    println!("Currently loaned: {}", library.currently_loaned().len());
+    println!("Loans for alice: {}", library.loans_for_borrower("alice").len());
+    if let Err(e) = library.check_in(isbn) {
+        println!("Could not check in {}: {}", isbn, e);
+    }
+
+    // Demonstrate bulk catalog import from raw text rows
+    let schema = ImportSchema {
+        columns: vec![
+            ("isbn".to_string(), Conversion::String),
+            ("title".to_string(), Conversion::String),
+            ("author".to_string(), Conversion::String),
+            ("year".to_string(), Conversion::Integer),
+        ],
+    };
+    let rows = vec![vec![
+        "9780140449136".to_string(),
+        "The Odyssey".to_string(),
+        "Homer".to_string(),
+        "1946".to_string(),
+    ]];
+    match library.import_records(&rows, &schema) {
+        Ok(n) => println!("Imported {} record(s)", n),
+        Err(e) => println!("Import failed: {}", e),
+    }
+
+    // Demonstrate BibTeX export/import as a citation interchange format
+    let bibtex = library.export_bibtex();
+    println!("{}", bibtex);
+    library.import_bibtex(&bibtex);
+    println!("Number of books after re-importing citations: {}", library.count_books());
+
+    // Demonstrate concurrent access through the library actor: many
+    // client tasks share a cloned `LibraryHandle` with no mutex in sight.
+    let handle = LibraryHandle::new(library);
+
+    let mut tasks = Vec::new();
     for i in 0..3 {
-        let library_clone = Arc::clone(&library_arc);
-        let handle = thread::spawn(move || {
-            let lib = library_clone.lock().unwrap();
-            println!("Thread {}: Library has {} books", i, lib.count_books());
-            thread::sleep(Duration::from_millis(100));
-        });
-        handles.push(handle);
+        let handle = handle.clone();
+        tasks.push(tokio::spawn(async move {
+            let count = handle.count_books().await;
+            println!("Task {}: Library has {} books", i, count);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }));
     }
-    
-    for handle in handles {
-        handle.join().unwrap();
+
+    for task in tasks {
+        task.await.unwrap();
     }
-    
+
+    // Demonstrate mutating and querying the actor through the handle itself
+    handle.add_book(LoanableItem::new_dvd("0097360817606", "Inception", 2010, "Christopher Nolan", 148)).await;
+    if let Err(e) = handle.check_out("9780743273565", "bob", 7).await {
+        println!("Could not check out via handle: {}", e);
+    }
+    println!("Currently loaned (via handle): {}", handle.currently_loaned().await.len());
+    println!("Loans for bob (via handle): {}", handle.loans_for_borrower("bob").await.len());
+    if let Err(e) = handle.check_in("9780743273565").await {
+        println!("Could not check in via handle: {}", e);
+    }
+    println!("Library has {} books after handle updates", handle.count_books().await);
+
     // Final message
     println!("Library program completed successfully!");
 }
\ No newline at end of file